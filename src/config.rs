@@ -0,0 +1,39 @@
+//! Variant/registry sync policy, loaded from a TOML config file.
+//!
+//! This replaces the shell script that used to encode, for each Fedora
+//! atomic-desktop variant, which architectures it ships on and which
+//! registries it gets pushed to.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncConfig {
+    /// The Fedora version that should also be tagged `latest`
+    pub current_stable: String,
+    /// The Fedora version that should also be tagged `rawhide`
+    pub current_rawhide: String,
+    pub variants: BTreeMap<String, Variant>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Variant {
+    /// A refspec that supports globs, e.g. `fedora/36/*/updates/silverblue`
+    pub refglob: String,
+    /// Architectures this variant is built for; refs for other
+    /// architectures are ignored even if `refglob` matches them
+    pub architectures: HashSet<String>,
+    /// Registries to push to; the first is canonical and is what's
+    /// inspected to decide which architectures need rebuilding
+    pub registries: Vec<String>,
+}
+
+impl SyncConfig {
+    pub fn load(path: &Utf8Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path).with_context(|| format!("Reading {path}"))?;
+        toml::from_str(&data).with_context(|| format!("Parsing {path}"))
+    }
+}
@@ -0,0 +1,204 @@
+//! Turning ostree commits into container images.
+//!
+//! This wraps `ostree-ext`'s in-process encapsulation of an ostree commit
+//! into a single-arch OCI image, and `containers-image-proxy` for read-only
+//! inspection of a remote manifest list, so that neither of those steps
+//! requires shelling out to `rpm-ostree container-encapsulate`/`skopeo
+//! copy`/`skopeo inspect` any more. Manifest-list assembly (create, amend,
+//! remove, add, annotate, push) has no equivalent in either library yet, so
+//! `main.rs` still shells out to `podman manifest` for that part of `sync` —
+//! a `podman` binary is a required runtime dependency for `sync` and
+//! `sync-config`. So is `skopeo`, which `containers-image-proxy` starts as
+//! its helper process for inspection; an `ostree` binary is not needed.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{Context, Result};
+use containers_image_proxy::ImageProxy;
+use ostree_ext::container::{Config, ExportOpts, ImageReference, Transport};
+
+/// The OCI layer compression to use when encapsulating. `ZstdChunked`
+/// produces content-addressed, partially-pullable layers, which is the
+/// win for base-image streams that only change incrementally; `Gzip`
+/// remains the default for maximum client compatibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CompressionFormat {
+    Gzip,
+    Zstd,
+    ZstdChunked,
+}
+
+impl CompressionFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::ZstdChunked => "zstd:chunked",
+        }
+    }
+}
+
+impl Default for CompressionFormat {
+    fn default() -> Self {
+        Self::Gzip
+    }
+}
+
+impl fmt::Display for CompressionFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One architecture's entry in a remote manifest list: its image digest, and
+/// the `version` annotation recording the ostree commit it was built from.
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub digest: String,
+    pub version: Option<String>,
+}
+
+/// Map an ostree/RPM architecture name (as found in `ARCHITECTURES`) to the
+/// Go-style OCI platform triple (`os`, `architecture`, `variant`) that
+/// registries expect a manifest list entry's descriptor to carry.
+pub fn oci_platform(arch: &str) -> Result<(&'static str, &'static str, Option<&'static str>)> {
+    Ok(match arch {
+        "x86_64" => ("linux", "amd64", None),
+        "aarch64" => ("linux", "arm64", Some("v8")),
+        "ppc64le" => ("linux", "ppc64le", None),
+        "s390x" => ("linux", "s390x", None),
+        other => anyhow::bail!("No OCI platform mapping for architecture {other}"),
+    })
+}
+
+/// The inverse of [`oci_platform`], used to key manifest list entries read
+/// back off a registry by ostree arch rather than by GOARCH.
+fn ostree_arch(architecture: &str, variant: Option<&str>) -> Option<&'static str> {
+    match (architecture, variant) {
+        ("amd64", _) => Some("x86_64"),
+        ("arm64", _) => Some("aarch64"),
+        ("ppc64le", _) => Some("ppc64le"),
+        ("s390x", _) => Some("s390x"),
+        _ => None,
+    }
+}
+
+/// A `containers-storage:` reference for a single architecture's build of a
+/// tag, e.g. `containers-storage:quay.io/fedora/silverblue-x86_64-36`.
+pub fn storage_reference(dest: &str, arch: &str, tag: &str) -> ImageReference {
+    ImageReference {
+        transport: Transport::ContainerStorage,
+        name: format!("{dest}-{arch}-{tag}"),
+    }
+}
+
+/// Encapsulate `ostree_ref`'s current commit in `repo` into an OCI image and
+/// write it to `dest`, returning the resulting image digest.
+pub fn encapsulate(
+    repo: &ostree::Repo,
+    ostree_ref: &str,
+    dest: &ImageReference,
+    compression: CompressionFormat,
+) -> Result<String> {
+    // `Config` is baked-in OCI image config (labels, cmd, etc); the layer
+    // compression to use is an export-time knob, so it belongs on
+    // `ExportOpts` instead.
+    let config = Config::default();
+    let opts = ExportOpts {
+        compression_format: Some(compression.as_str().to_string()),
+        ..Default::default()
+    };
+    let rt = tokio::runtime::Runtime::new().context("Initializing async runtime")?;
+    rt.block_on(ostree_ext::container::encapsulate(
+        repo,
+        ostree_ref,
+        &config,
+        Some(&opts),
+        dest,
+    ))
+    .with_context(|| format!("Encapsulating {ostree_ref} to {dest}"))
+}
+
+/// Whether an error from opening an image is a genuine "it doesn't exist
+/// yet" response, as opposed to an auth failure, network error, or other
+/// problem that should be surfaced rather than treated as a first sync.
+fn is_not_found(e: &impl std::fmt::Display) -> bool {
+    let message = e.to_string().to_lowercase();
+    ["manifest unknown", "name unknown", "not found", "404"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+/// Inspect `image` (expected to be a manifest list) and return its entries
+/// keyed by architecture, without downloading any layers. Returns `None` if
+/// the image genuinely doesn't exist yet, which is the common case for a
+/// first sync; any other failure (auth, network, a malformed reference) is
+/// propagated instead of being mistaken for that.
+pub fn inspect_manifest_versions(image: &str) -> Result<Option<BTreeMap<String, ManifestEntry>>> {
+    let rt = tokio::runtime::Runtime::new().context("Initializing async runtime")?;
+    rt.block_on(async {
+        let proxy = ImageProxy::new().await.context("Starting image proxy")?;
+        let opened = match proxy.open_image(image).await {
+            Ok(opened) => opened,
+            Err(e) if is_not_found(&e) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Opening {image}")),
+        };
+        let (_digest, raw_manifest) = proxy
+            .fetch_manifest(&opened)
+            .await
+            .with_context(|| format!("Fetching manifest for {image}"))?;
+        proxy.close_image(opened).await?;
+
+        let index: serde_json::Value = serde_json::from_slice(&raw_manifest)
+            .with_context(|| format!("Parsing manifest list for {image}"))?;
+        let mut entries = BTreeMap::new();
+        for entry in index["manifests"].as_array().into_iter().flatten() {
+            let Some(architecture) = entry["platform"]["architecture"].as_str() else {
+                continue;
+            };
+            let variant = entry["platform"]["variant"].as_str();
+            let Some(arch) = ostree_arch(architecture, variant) else {
+                continue;
+            };
+            let Some(digest) = entry["digest"].as_str() else {
+                continue;
+            };
+            let version = entry["annotations"]["version"]
+                .as_str()
+                .map(|s| s.to_string());
+            entries.insert(
+                arch.to_string(),
+                ManifestEntry {
+                    digest: digest.to_string(),
+                    version,
+                },
+            );
+        }
+        Ok(Some(entries))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oci_platform_round_trips_through_ostree_arch() {
+        for arch in ["x86_64", "aarch64", "ppc64le", "s390x"] {
+            let (os, goarch, variant) = oci_platform(arch).unwrap();
+            assert_eq!(os, "linux");
+            assert_eq!(ostree_arch(goarch, variant), Some(arch));
+        }
+    }
+
+    #[test]
+    fn oci_platform_rejects_unknown_arch() {
+        assert!(oci_platform("mips").is_err());
+    }
+
+    #[test]
+    fn ostree_arch_rejects_unknown_goarch() {
+        assert_eq!(ostree_arch("mips", None), None);
+    }
+}
@@ -1,14 +1,17 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::process::Command;
 
-use anyhow::Result;
-use camino::{Utf8Path, Utf8PathBuf};
+use anyhow::{Context, Result};
+use camino::Utf8PathBuf;
 use clap::Parser;
 use once_cell::sync::Lazy;
+use ostree::{gio, glib};
+
+mod config;
+mod container;
 
 /// Arbitrary list; this is all that's shipped in the ostree repo
 /// for Fedora today.
-#[allow(dead_code)]
 static ARCHITECTURES: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     ["aarch64", "x86_64", "ppc64le", "s390x"]
         .into_iter()
@@ -26,6 +29,16 @@ struct RepoOpts {
     remote: String,
 }
 
+impl RepoOpts {
+    /// Open the ostree repository, without fetching anything yet.
+    fn open(&self) -> Result<ostree::Repo> {
+        let repo = ostree::Repo::new_for_path(&self.repo);
+        repo.open(gio::Cancellable::NONE)
+            .with_context(|| format!("Opening ostree repo at {}", self.repo))?;
+        Ok(repo)
+    }
+}
+
 #[derive(Debug, Parser)]
 struct Opt {
     // /// The ostree container format version
@@ -46,19 +59,117 @@ enum Cmd {
         /// fedora/36/*/updates
         refs: String,
     },
+
+    /// Encapsulate matched ostree refs into container images, and
+    /// push them to a destination as per-tag manifest lists.
+    ///
+    /// Requires a `podman` binary on `PATH`: manifest-list assembly still
+    /// shells out to `podman manifest`, since neither `ostree-ext` nor
+    /// `containers-image-proxy` implement that yet.
+    Sync {
+        #[clap(flatten)]
+        repo: RepoOpts,
+
+        /// A refspec that supports globs; for example,
+        /// fedora/36/*/updates/silverblue
+        refs: String,
+
+        /// Destination image, for example quay.io/fedora/silverblue
+        dest: String,
+
+        /// Build and push a tag even if its architectures' ostree commits
+        /// carry different `version` metadata
+        #[clap(long)]
+        allow_version_mismatch: bool,
+
+        /// OCI layer compression to use when encapsulating
+        #[clap(long, value_enum, default_value_t = container::CompressionFormat::Gzip)]
+        compression_format: container::CompressionFormat,
+    },
+
+    /// Sync every variant described in a TOML config to its configured
+    /// registries, applying `latest`/`rawhide` alias tags by release policy.
+    ///
+    /// Like `sync`, this requires a `podman` binary on `PATH` for manifest-
+    /// list assembly.
+    SyncConfig {
+        #[clap(flatten)]
+        repo: RepoOpts,
+
+        /// Path to a TOML file describing variants, architectures and
+        /// registries; see `config::SyncConfig`
+        #[clap(long, value_parser, required = true)]
+        config: Utf8PathBuf,
+
+        /// Build and push a tag even if its architectures' ostree commits
+        /// carry different `version` metadata
+        #[clap(long)]
+        allow_version_mismatch: bool,
+
+        /// OCI layer compression to use when encapsulating
+        #[clap(long, value_enum, default_value_t = container::CompressionFormat::Gzip)]
+        compression_format: container::CompressionFormat,
+    },
 }
 
 impl Opt {
     fn run(self) -> Result<()> {
         match &self.cmd {
             Cmd::Fetch { repo, refs } => self.fetch(&repo, refs),
+            Cmd::Sync {
+                repo,
+                refs,
+                dest,
+                allow_version_mismatch,
+                compression_format,
+            } => self.sync(
+                &repo,
+                refs,
+                std::slice::from_ref(dest),
+                None,
+                None,
+                None,
+                *allow_version_mismatch,
+                *compression_format,
+            ),
+            Cmd::SyncConfig {
+                repo,
+                config,
+                allow_version_mismatch,
+                compression_format,
+            } => self.sync_config(&repo, config, *allow_version_mismatch, *compression_format),
+        }
+    }
+
+    /// Sync every variant in `config` to its configured registries.
+    fn sync_config(
+        &self,
+        repo: &RepoOpts,
+        config_path: &Utf8PathBuf,
+        allow_version_mismatch: bool,
+        compression_format: container::CompressionFormat,
+    ) -> Result<()> {
+        let config = config::SyncConfig::load(config_path)?;
+        for (name, variant) in &config.variants {
+            println!("Syncing variant {name}");
+            self.sync(
+                repo,
+                &variant.refglob,
+                &variant.registries,
+                Some(&variant.architectures),
+                Some(&config.current_stable),
+                Some(&config.current_rawhide),
+                allow_version_mismatch,
+                compression_format,
+            )
+            .with_context(|| format!("Syncing variant {name}"))?;
         }
+        Ok(())
     }
 
     fn fetch(&self, repo: &RepoOpts, refglob: &str) -> Result<()> {
-        let repopath = &repo.repo;
-        let remotename = &repo.remote;
-        let all_refs = remote_list(repo.repo.as_path(), remotename)?;
+        let ostree_repo = repo.open()?;
+        let all_refs = remote_list(&ostree_repo, &repo.remote)?;
         let all_refs = all_refs.iter().map(|s| s.as_str()).collect::<Vec<_>>();
 
         let targets = glob_match_refs(&all_refs, refglob);
@@ -67,16 +178,274 @@ impl Opt {
         println!("Filtered {all_refs_count} refs to:");
         println!("{targets:?}");
 
-        for ostreeref in targets {
-            let status = Command::new("ostree")
-                .args([
-                    &format!("--repo={repopath}"),
-                    "pull",
-                    &format!("{remotename}:{ostreeref}"),
-                ])
-                .status()?;
-            if !status.success() {
-                anyhow::bail!("Failed to fetch: {status:?}");
+        for (ostreeref, _arch) in targets {
+            pull_ref(&ostree_repo, &repo.remote, ostreeref)?;
+        }
+
+        Ok(())
+    }
+
+    /// Build and push manifest-listed container images for every tag implied
+    /// by `refglob`, collapsing refs that differ only in architecture into a
+    /// single manifest list per tag. An architecture is only (re-)fetched and
+    /// (re-)encapsulated when it's missing from the destination manifest list
+    /// or its stored `version` annotation doesn't match the current commit.
+    ///
+    /// The resulting manifest list is pushed to every registry in
+    /// `registries` (the first is canonical, and is what idempotency is
+    /// checked against). If `current_stable`/`current_rawhide` are given and
+    /// a tag's Fedora version matches one of them, it's also pushed as
+    /// `latest`/`rawhide` respectively. When `allowed_arches` is set, refs
+    /// for any other architecture are ignored even if `refglob` matches them.
+    #[allow(clippy::too_many_arguments)]
+    fn sync(
+        &self,
+        repo: &RepoOpts,
+        refglob: &str,
+        registries: &[String],
+        allowed_arches: Option<&HashSet<String>>,
+        current_stable: Option<&str>,
+        current_rawhide: Option<&str>,
+        allow_version_mismatch: bool,
+        compression_format: container::CompressionFormat,
+    ) -> Result<()> {
+        let canonical = registries
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No registries configured"))?;
+
+        let ostree_repo = repo.open()?;
+        let all_refs = remote_list(&ostree_repo, &repo.remote)?;
+        let all_refs = all_refs.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+
+        let targets = glob_match_refs(&all_refs, refglob);
+        if targets.is_empty() {
+            anyhow::bail!("No refs matched glob: {refglob}");
+        }
+
+        // Group the matched refs by destination tag, keyed by architecture so
+        // that refs which differ only in arch collapse into one manifest list.
+        let mut tags: BTreeMap<String, TagGroup> = BTreeMap::new();
+        for (ostreeref, arch) in targets {
+            if allowed_arches.is_some_and(|allowed| !allowed.contains(arch)) {
+                continue;
+            }
+            let info = RefInfo::parse(ostreeref)?;
+            let group = tags.entry(info.tag()).or_insert_with(|| TagGroup {
+                fedora_version: info.fedora_version.to_string(),
+                arches: BTreeMap::new(),
+            });
+            group.arches.insert(arch, ostreeref);
+        }
+
+        if tags.is_empty() {
+            anyhow::bail!(
+                "No refs matched glob {refglob:?} for the allowed architectures; \
+                 check the variant's `architectures` list against what the remote actually has"
+            );
+        }
+
+        for (tag, group) in tags {
+            let arches = group.arches;
+            let dest_ref = format!("{canonical}:{tag}");
+            let existing_manifest = container::inspect_manifest_versions(&dest_ref)
+                .with_context(|| format!("Inspecting {dest_ref}"))?;
+            let existing = existing_manifest.clone().unwrap_or_default();
+
+            // Pulling commit-only metadata is cheap, so do it for every
+            // architecture up front to decide what actually needs a rebuild.
+            let mut versions: BTreeMap<&str, String> = BTreeMap::new();
+            let mut stale: BTreeMap<&str, &str> = BTreeMap::new();
+            for (&arch, &ostreeref) in &arches {
+                pull_ref_commit_only(&ostree_repo, &repo.remote, ostreeref)?;
+                let checksum = ostree_repo
+                    .resolve_rev(&format!("{}:{ostreeref}", repo.remote), false)?
+                    .ok_or_else(|| anyhow::anyhow!("{ostreeref} missing after pull"))?;
+                let version = commit_metadata_version(&ostree_repo, &checksum)?;
+
+                let unchanged = existing
+                    .get(arch)
+                    .is_some_and(|e| e.version.is_some() && e.version == version);
+                if !unchanged {
+                    stale.insert(arch, ostreeref);
+                }
+                if let Some(version) = version {
+                    versions.insert(arch, version);
+                }
+            }
+
+            let distinct_versions: HashSet<&str> =
+                versions.values().map(|s| s.as_str()).collect();
+            if distinct_versions.len() > 1 && !allow_version_mismatch {
+                anyhow::bail!(
+                    "{tag}'s architectures have mismatched commit versions {versions:?}; \
+                     pass --allow-version-mismatch to build anyway"
+                );
+            }
+
+            // Seed the local manifest list from the canonical registry (if it
+            // already exists) so that unchanged architectures are preserved,
+            // then only touch the ones that are actually stale. We need this
+            // local manifest regardless of whether anything is stale, since
+            // it's also what gets pushed to secondary registries/aliases
+            // below. Whether the registry actually has a list yet was
+            // already determined above by `inspect_manifest_versions`, which
+            // (unlike a podman exit code) distinguishes "doesn't exist yet"
+            // from a transient failure, so trust that instead of falling
+            // back to a bare `create` on any `--amend` failure.
+            if existing_manifest.is_some() {
+                let status = Command::new("podman")
+                    .args([
+                        "manifest",
+                        "create",
+                        "--amend",
+                        "--all",
+                        &dest_ref,
+                        &format!("docker://{dest_ref}"),
+                    ])
+                    .status()?;
+                if !status.success() {
+                    anyhow::bail!(
+                        "Failed to amend existing manifest list {dest_ref}: {status:?}"
+                    );
+                }
+            } else {
+                let status = Command::new("podman")
+                    .args(["manifest", "create", &dest_ref])
+                    .status()?;
+                if !status.success() {
+                    anyhow::bail!("Failed to create manifest list: {status:?}");
+                }
+            }
+
+            // Only carry forward architectures that are still part of this
+            // variant; one that's been dropped from `refglob`/
+            // `allowed_arches` is never in `stale` (which is only computed
+            // over `arches`), so its entry is removed from the seeded local
+            // list here; otherwise it would be re-seeded from `existing` and
+            // pushed forever.
+            let mut final_digests: BTreeMap<&str, String> = BTreeMap::new();
+            for (&arch, entry) in &existing {
+                if !arches.contains_key(arch) {
+                    let status = Command::new("podman")
+                        .args(["manifest", "remove", &dest_ref, &entry.digest])
+                        .status()?;
+                    if !status.success() {
+                        anyhow::bail!("Failed to remove retired {arch} entry: {status:?}");
+                    }
+                } else if !stale.contains_key(arch) {
+                    final_digests.insert(arch, entry.digest.clone());
+                }
+            }
+
+            if stale.is_empty() {
+                println!("{dest_ref} is up to date ({} architectures)", arches.len());
+            } else {
+                println!(
+                    "Building {dest_ref}: {} of {} architectures changed",
+                    stale.len(),
+                    arches.len()
+                );
+
+                for (arch, ostreeref) in stale {
+                    if let Some(old) = existing.get(arch) {
+                        let status = Command::new("podman")
+                            .args(["manifest", "remove", &dest_ref, &old.digest])
+                            .status()?;
+                        if !status.success() {
+                            anyhow::bail!("Failed to remove stale {arch} entry: {status:?}");
+                        }
+                    }
+
+                    pull_ref(&ostree_repo, &repo.remote, ostreeref)?;
+
+                    let storage_image = container::storage_reference(canonical, arch, &tag);
+                    let digest = container::encapsulate(
+                        &ostree_repo,
+                        ostreeref,
+                        &storage_image,
+                        compression_format,
+                    )
+                    .with_context(|| format!("Encapsulating {ostreeref}"))?;
+                    println!("Encapsulated {ostreeref} as {storage_image} ({digest})");
+                    final_digests.insert(arch, digest);
+
+                    // containers-image-proxy doesn't yet expose manifest-list
+                    // assembly, so that bookkeeping still goes through podman.
+                    let status = Command::new("podman")
+                        .args(["manifest", "add", &dest_ref, &storage_image.to_string()])
+                        .status()?;
+                    if !status.success() {
+                        anyhow::bail!(
+                            "Failed to add {arch} to manifest list {dest_ref}: {status:?}"
+                        );
+                    }
+
+                    if let Some(version) = versions.get(arch) {
+                        let (os, goarch, variant) = container::oci_platform(arch)?;
+                        let mut annotate_args = vec![
+                            "manifest".to_string(),
+                            "annotate".to_string(),
+                            "--os".to_string(),
+                            os.to_string(),
+                            "--arch".to_string(),
+                            goarch.to_string(),
+                        ];
+                        if let Some(variant) = variant {
+                            annotate_args.push("--variant".to_string());
+                            annotate_args.push(variant.to_string());
+                        }
+                        annotate_args.push(format!("--annotation=version={version}"));
+                        annotate_args.push(dest_ref.clone());
+                        annotate_args.push(storage_image.to_string());
+
+                        let status = Command::new("podman").args(&annotate_args).status()?;
+                        if !status.success() {
+                            anyhow::bail!(
+                                "Failed to annotate {arch} with version {version}: {status:?}"
+                            );
+                        }
+                    }
+                }
+            }
+
+            // Push the tag to every configured registry, plus `latest`/
+            // `rawhide` aliases where release policy says this tag qualifies.
+            // Staleness was only ever checked against the canonical registry,
+            // so a secondary registry or a newly-qualifying alias may not
+            // have this content yet even when nothing was rebuilt; inspect
+            // each target rather than assuming "nothing stale" means "nothing
+            // to push".
+            let expected_digests: HashSet<&str> =
+                final_digests.values().map(|s| s.as_str()).collect();
+            let mut push_tags = vec![tag.clone()];
+            if current_stable == Some(group.fedora_version.as_str()) {
+                push_tags.push("latest".to_string());
+            }
+            if current_rawhide == Some(group.fedora_version.as_str()) {
+                push_tags.push("rawhide".to_string());
+            }
+
+            for registry in registries {
+                for push_tag in &push_tags {
+                    let target = format!("{registry}:{push_tag}");
+                    let up_to_date = container::inspect_manifest_versions(&target)
+                        .with_context(|| format!("Inspecting {target}"))?
+                        .is_some_and(|remote| {
+                            let remote_digests: HashSet<&str> =
+                                remote.values().map(|e| e.digest.as_str()).collect();
+                            remote_digests == expected_digests
+                        });
+                    if up_to_date {
+                        continue;
+                    }
+
+                    let status = Command::new("podman")
+                        .args(["manifest", "push", &dest_ref, &format!("docker://{target}")])
+                        .status()?;
+                    if !status.success() {
+                        anyhow::bail!("Failed to push manifest list {dest_ref} to {target}: {status:?}");
+                    }
+                }
             }
         }
 
@@ -84,45 +453,128 @@ impl Opt {
     }
 }
 
-fn remote_list(repo: &Utf8Path, remote: &str) -> Result<Vec<String>> {
-    let o = Command::new("ostree")
-        .args([&format!("--repo={repo}"), "remote", "refs", remote])
-        .stderr(std::process::Stdio::inherit())
-        .output()?;
-    if !o.status.success() {
-        anyhow::bail!("failed to run ostree remote list: {:?}", o.status)
-    }
-    let o = String::from_utf8(o.stdout)?;
-    o.lines()
-        .map(|v| {
-            let name = v
-                .split_once(':')
-                .ok_or_else(|| anyhow::anyhow!("Invalid remote line: {v}"))?
-                .1;
-            Ok(name.to_string())
+/// The refs matched for one destination tag, grouped by architecture.
+struct TagGroup<'a> {
+    fedora_version: String,
+    arches: BTreeMap<&'a str, &'a str>,
+}
+
+/// The pieces of an ostree ref relevant to building a container tag, e.g.
+/// `fedora/36/x86_64/updates/silverblue` or `fedora/36/x86_64/silverblue`.
+#[derive(Debug)]
+struct RefInfo<'a> {
+    fedora_version: &'a str,
+    /// `testing`, `updates`, or `None` for the stable stream.
+    stream: Option<&'a str>,
+}
+
+impl<'a> RefInfo<'a> {
+    fn parse(ostreeref: &'a str) -> Result<Self> {
+        let parts = ostreeref.split('/').collect::<Vec<_>>();
+        let (fedora_version, stream) = match parts.as_slice() {
+            [_, fedora_version, _arch, collection] => {
+                let _ = collection;
+                (*fedora_version, None)
+            }
+            [_, fedora_version, _arch, stream, collection] => {
+                let _ = collection;
+                (*fedora_version, Some(*stream))
+            }
+            _ => anyhow::bail!("Unrecognized ref format: {ostreeref}"),
+        };
+        Ok(Self {
+            fedora_version,
+            stream,
         })
-        .collect()
+    }
+
+    /// The destination tag for this ref, e.g. `36`, `36-testing`, `36-updates`.
+    fn tag(&self) -> String {
+        match self.stream {
+            Some(stream) => format!("{}-{stream}", self.fedora_version),
+            None => self.fedora_version.to_string(),
+        }
+    }
 }
 
-fn glob_match_refs<'a>(all_refs: &'a [&str], glob: &str) -> Vec<&'a str> {
+/// Pull a single ref's ostree commit from `remote` into `repo`.
+fn pull_ref(repo: &ostree::Repo, remote: &str, ostreeref: &str) -> Result<()> {
+    repo.pull(
+        remote,
+        &[ostreeref],
+        ostree::RepoPullFlags::NONE,
+        None,
+        gio::Cancellable::NONE,
+    )
+    .with_context(|| format!("Pulling {remote}:{ostreeref}"))?;
+    Ok(())
+}
+
+/// Pull just the commit object (not its content) for `ostreeref`, cheaply
+/// enough to inspect its `version` metadata before deciding whether a full
+/// fetch is warranted.
+fn pull_ref_commit_only(repo: &ostree::Repo, remote: &str, ostreeref: &str) -> Result<()> {
+    repo.pull(
+        remote,
+        &[ostreeref],
+        ostree::RepoPullFlags::COMMIT_ONLY,
+        None,
+        gio::Cancellable::NONE,
+    )
+    .with_context(|| format!("Pulling commit metadata for {remote}:{ostreeref}"))?;
+    Ok(())
+}
+
+/// Read the `version` key out of a commit's metadata, as set by
+/// `rpm-ostree compose` (and mirrored into the manifest list as an
+/// `--annotation version=...`).
+fn commit_metadata_version(repo: &ostree::Repo, checksum: &str) -> Result<Option<String>> {
+    let (commit, _state) = repo.load_commit(checksum)?;
+    let metadata = commit.child_value(0);
+    Ok(metadata
+        .lookup_value("version", Some(glib::VariantTy::STRING))
+        .and_then(|v| v.str().map(|s| s.to_string())))
+}
+
+/// List the refs advertised by `remote`, using the repo's own ref-list
+/// rather than shelling out and parsing `ostree remote refs` text output.
+fn remote_list(repo: &ostree::Repo, remote: &str) -> Result<Vec<String>> {
+    let refs = repo
+        .remote_list_refs(remote, gio::Cancellable::NONE)
+        .with_context(|| format!("Listing refs for remote {remote}"))?;
+    let mut refs = refs.keys().map(|s| s.to_string()).collect::<Vec<_>>();
+    refs.sort();
+    Ok(refs)
+}
+
+/// Filter `all_refs` down to those matching the glob, returning each
+/// matched ref paired with the architecture captured by its (sole)
+/// wildcard segment, e.g. `fedora/36/*/silverblue` captures `x86_64`. A
+/// matched wildcard segment that isn't a known architecture (from
+/// `ARCHITECTURES`) is dropped rather than returned.
+fn glob_match_refs<'a>(all_refs: &'a [&str], glob: &str) -> Vec<(&'a str, &'a str)> {
     let parts = glob.split('/').collect::<Vec<_>>();
+    let wildcard_idx = parts.iter().position(|&p| p == "*");
     all_refs
         .iter()
-        .filter(|v| {
+        .filter_map(|v| {
             let v_parts = v.split('/').collect::<Vec<_>>();
             if parts.len() != v_parts.len() {
-                return false;
+                return None;
             }
 
             for (&v, &g) in v_parts.iter().zip(parts.iter()) {
                 if g != "*" && v != g {
-                    return false;
+                    return None;
                 }
             }
 
-            true
+            let arch = wildcard_idx.map(|i| v_parts[i]).unwrap_or_default();
+            if !arch.is_empty() && !ARCHITECTURES.contains(arch) {
+                return None;
+            }
+            Some((*v, arch))
         })
-        .copied()
         .collect()
 }
 
@@ -130,36 +582,60 @@ fn main() -> anyhow::Result<()> {
     let opts = Opt::from_args();
 
     opts.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ref_info_parses_four_segment_stable_ref() {
+        let info = RefInfo::parse("fedora/36/x86_64/silverblue").unwrap();
+        assert_eq!(info.fedora_version, "36");
+        assert_eq!(info.stream, None);
+        assert_eq!(info.tag(), "36");
+    }
 
-    // Take as input a set of refs for example given
-    //
-    // fedora:fedora/36/aarch64/silverblue
-    // fedora:fedora/36/aarch64/testing/silverblue
-    // fedora:fedora/36/aarch64/updates/silverblue
-    // fedora:fedora/36/ppc64le/silverblue
-    // fedora:fedora/36/ppc64le/testing/silverblue
-    // fedora:fedora/36/ppc64le/updates/silverblue
-    // fedora:fedora/36/x86_64/silverblue
-    // fedora:fedora/36/x86_64/testing/silverblue
-    // fedora:fedora/36/x86_64/updates/silverblue
-    //
-    // We want to generate 3 containers:
-    // quay.io/fedora/silverblue:36
-    // quay.io/fedora/silverblue:36-testing
-    // quay.io/fedora/silverblue:36-updates
-    //
-    // That should be manifest listed.
-    //
-    // Fetch the ostree commits only, and inspect their versions.  Error out
-    // by default if they are different?
-    //
-    // Check if there's an existing manifest list image, i.e. skopeo inspect
-    // or use the container proxy.
-    // If there are any missing missing manifest architecture entries,
-    // *or* if the manifest list version is different than the commit version,
-    // fetch the target ostree commit (entirely).
-    // run rpm-ostree container-encapsulate on it to an oci dir, then copy to containers-storage
-    //
-    // podman manifest create quay.io/fedora/silverblue:36
-    // for arch in arches; podman manifest annotate --annotation version=version; done
+    #[test]
+    fn ref_info_parses_five_segment_stream_ref() {
+        let info = RefInfo::parse("fedora/36/x86_64/updates/silverblue").unwrap();
+        assert_eq!(info.fedora_version, "36");
+        assert_eq!(info.stream, Some("updates"));
+        assert_eq!(info.tag(), "36-updates");
+    }
+
+    #[test]
+    fn ref_info_rejects_unrecognized_shape() {
+        assert!(RefInfo::parse("fedora/36").is_err());
+    }
+
+    #[test]
+    fn glob_match_refs_captures_wildcard_arch() {
+        let all_refs = [
+            "fedora/36/x86_64/silverblue",
+            "fedora/36/aarch64/silverblue",
+            "fedora/35/x86_64/silverblue",
+        ];
+        let mut matched = glob_match_refs(&all_refs, "fedora/36/*/silverblue");
+        matched.sort();
+        assert_eq!(
+            matched,
+            vec![
+                ("fedora/36/aarch64/silverblue", "aarch64"),
+                ("fedora/36/x86_64/silverblue", "x86_64"),
+            ]
+        );
+    }
+
+    #[test]
+    fn glob_match_refs_drops_unknown_architecture() {
+        let all_refs = ["fedora/36/mips/silverblue"];
+        assert_eq!(glob_match_refs(&all_refs, "fedora/36/*/silverblue"), vec![]);
+    }
+
+    #[test]
+    fn glob_match_refs_requires_matching_segment_count() {
+        let all_refs = ["fedora/36/x86_64/updates/silverblue"];
+        assert_eq!(glob_match_refs(&all_refs, "fedora/36/*/silverblue"), vec![]);
+    }
 }